@@ -0,0 +1,406 @@
+//! Thread std-like interface
+//!
+//! Based on Processor. Used in kernel.
+//!
+//! You need to implement the following functions before use:
+//! - `processor`: Get a reference of the current `Processor`
+//! - `new_kernel_context`: Construct a `Context` of the new kernel thread
+//! - `new_kernel_context_with_stack`: Same, with a requested stack size
+//!   (used by `Builder::stack_size`)
+//! - `new_user_thread_context`: Construct a `Context` of a new user thread
+//!   (used by `clone`)
+
+pub mod sync;
+mod scoped;
+
+pub use self::scoped::{scope, Scope, ScopedJoinHandle};
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use core::any::Any;
+use core::marker::PhantomData;
+use core::time::Duration;
+use log::*;
+use spin::Mutex;
+use lazy_static::lazy_static;
+use crate::processor::*;
+use crate::process_manager::*;
+
+#[linkage = "weak"]
+#[no_mangle]
+/// Get a reference of the current `Processor`
+fn processor() -> &'static Processor {
+    unimplemented!("thread: Please implement and export `processor`")
+}
+
+#[linkage = "weak"]
+#[no_mangle]
+/// Construct a `Context` of the new kernel thread
+fn new_kernel_context(_entry: extern fn(usize) -> !, _arg: usize) -> Box<Context> {
+    unimplemented!("thread: Please implement and export `new_kernel_context`")
+}
+
+#[linkage = "weak"]
+#[no_mangle]
+/// Construct a `Context` of the new kernel thread with a requested stack size
+fn new_kernel_context_with_stack(_entry: extern fn(usize) -> !, _arg: usize, _stack_size: usize) -> Box<Context> {
+    unimplemented!("thread: Please implement and export `new_kernel_context_with_stack`")
+}
+
+#[linkage = "weak"]
+#[no_mangle]
+/// Construct a `Context` for a new user thread that shares the caller's address
+/// space (same page table / `satp`) but runs on a fresh user stack.
+fn new_user_thread_context(_entry: extern fn(usize) -> !, _arg: usize, _stack_top: usize) -> Box<Context> {
+    unimplemented!("thread: Please implement and export `new_user_thread_context`")
+}
+
+/// Exit code recorded when a thread panics.
+///
+/// A thread that returns normally records the heap pointer to its boxed return
+/// value as its exit code, which is always a real allocation address. The
+/// failure markers are therefore placed at the very top of the address space,
+/// where no heap pointer (nor the dangling pointer of a zero-sized `T`) can
+/// land, so `join` can tell success from failure unambiguously.
+pub const EXIT_PANIC: usize = core::usize::MAX;
+/// Exit code recorded when a thread is killed before producing a return value.
+pub const EXIT_KILLED: usize = core::usize::MAX - 1;
+
+/// The reason a joined thread did not produce a return value.
+#[derive(Debug)]
+pub enum ThreadError {
+    /// The thread panicked while running.
+    Panicked,
+    /// The thread was killed before it returned.
+    Killed,
+    /// No thread with the handle's PID exists (already reaped, or never ran).
+    NoSuchThread,
+}
+
+/// Records a failure exit code (e.g. [`EXIT_PANIC`]) for the current thread, so
+/// `join` reports an `Err` instead of reconstructing a missing return value.
+///
+/// Called from the panic path (the `lang` panic handler); a kill goes through
+/// [`kill`] instead.
+pub fn exit_with_failure(code: usize) {
+    // Release the failing thread's per-PID storage, which the normal-exit path
+    // in `kernel_thread_entry` does too; otherwise a panicking/killed thread
+    // leaks its thread-locals and debug name, and a later PID reuse inherits
+    // stale entries.
+    clear_thread_locals(current().id());
+    THREAD_NAMES.lock().remove(&current().id());
+    processor().manager().exit(current().id(), code);
+}
+
+/// Kills the thread `pid`, recording [`EXIT_KILLED`] so a `join` on it reports
+/// `Err(Killed)` rather than reconstructing a return value it never produced.
+///
+/// This is the kill side of the failure path described alongside
+/// [`exit_with_failure`]; the `sys_kill` handler routes here.
+pub fn kill(pid: usize) {
+    clear_thread_locals(pid);
+    THREAD_NAMES.lock().remove(&pid);
+    processor().manager().exit(pid, EXIT_KILLED);
+}
+
+
+/// Gets a handle to the thread that invokes it.
+pub fn current() -> Thread {
+    Thread { pid: processor().pid() }
+}
+
+/// Puts the current thread to sleep for the specified amount of time.
+pub fn sleep(dur: Duration) {
+    let time = dur_to_ticks(dur);
+    trace!("sleep: {:?} ticks", time);
+    processor().manager().sleep(current().id(), time);
+    park();
+
+    fn dur_to_ticks(dur: Duration) -> usize {
+        return dur.as_secs() as usize * 100 + dur.subsec_nanos() as usize / 10_000_000;
+    }
+}
+
+/// Spawns a new thread, returning a JoinHandle for it.
+///
+/// `F`: Type of the function `f`
+/// `T`: Type of the return value of `f`
+pub fn spawn<F, T>(f: F) -> JoinHandle<T>
+    where
+        F: Send + 'static + FnOnce() -> T,
+        T: Send + 'static,
+{
+    Builder::new().spawn(f).expect("failed to spawn thread")
+}
+
+/// Thread factory, which can be used in order to configure the properties of
+/// a new thread.
+///
+/// The two configurable properties are:
+/// - `name`: a debug name stored on the `Thread`, returned by `current().name()`;
+/// - `stack_size`: the size of the new thread's stack, in bytes.
+pub struct Builder {
+    name: Option<String>,
+    stack_size: Option<usize>,
+}
+
+impl Builder {
+    /// Generates the base configuration for spawning a thread, from which
+    /// configuration methods can be chained.
+    pub fn new() -> Builder {
+        Builder { name: None, stack_size: None }
+    }
+
+    /// Names the thread-to-be.
+    pub fn name(mut self, name: String) -> Builder {
+        self.name = Some(name);
+        self
+    }
+
+    /// Sets the size of the stack (in bytes) for the new thread.
+    pub fn stack_size(mut self, size: usize) -> Builder {
+        self.stack_size = Some(size);
+        self
+    }
+
+    /// Spawns a new thread by taking ownership of the `Builder`, returning a
+    /// `JoinHandle` for it. The `Result` mirrors `std::thread::Builder::spawn`;
+    /// this implementation has no failure path and always returns `Ok`.
+    pub fn spawn<F, T>(self, f: F) -> Result<JoinHandle<T>, ()>
+        where
+            F: Send + 'static + FnOnce() -> T,
+            T: Send + 'static,
+    {
+        // 注意到下面的问题：
+        // Processor只能从入口地址entry+参数arg创建新线程
+        // 而我们现在需要让它执行一个未知类型的（闭包）函数f
+
+        // 首先把函数本体（代码数据）置于堆空间中
+        let f = Box::into_raw(Box::new(f));
+
+        // 定义一个静态函数作为新线程的入口点
+        // 其参数是函数f在堆上的指针
+        // 这样我们就把函数f传到了一个静态函数内部
+        //
+        // 注意到它具有泛型参数，因此对每一次spawn调用，
+        // 由于F类型是独特的，因此都会生成一个新的kernel_thread_entry
+        extern fn kernel_thread_entry<F, T>(f: usize) -> !
+            where
+                F: Send + 'static + FnOnce() -> T,
+                T: Send + 'static,
+        {
+            // 在静态函数内部：
+            // 根据传进来的指针，恢复f
+            let f = unsafe { Box::from_raw(f as *mut F) };
+            // 调用f，并将其返回值也放在堆上
+            let ret = Box::new(f());
+            // 清理本地线程存储与调试名
+            clear_thread_locals(current().id());
+            THREAD_NAMES.lock().remove(&current().id());
+            // 让Processor退出当前线程
+            // 把f返回值在堆上的指针，以线程返回码的形式传递出去
+            let exit_code = Box::into_raw(ret) as usize;
+            processor().manager().exit(current().id(), exit_code);
+            processor().yield_now();
+            // 再也不会被调度回来了
+            unreachable!()
+        }
+
+        // 根据是否指定了栈大小，选择合适的上下文构造入口：
+        // 未指定时沿用原有的 new_kernel_context，保持对只实现了该弱符号的
+        // 内核的向后兼容；仅在显式请求栈大小时才走 _with_stack 分支。
+        let context = match self.stack_size {
+            Some(stack_size) =>
+                new_kernel_context_with_stack(kernel_thread_entry::<F, T>, f as usize, stack_size),
+            None =>
+                new_kernel_context(kernel_thread_entry::<F, T>, f as usize),
+        };
+        let pid = processor().manager().add(context, processor().pid());
+
+        // 记录调试名，使 current().name() 可以查询到
+        if let Some(name) = self.name {
+            THREAD_NAMES.lock().insert(pid, name);
+        }
+
+        Ok(JoinHandle {
+            thread: Thread { pid },
+            mark: PhantomData,
+        })
+    }
+}
+
+/// Creates a new user thread sharing the caller's address space.
+///
+/// Unlike [`spawn`], which runs a kernel closure, this is the kernel side of
+/// the `clone` syscall: the new task shares the caller's page table (so global
+/// data is shared) but starts executing at `entry` with `arg` in its argument
+/// register and `stack_top` as its stack pointer. Returns the new thread's id.
+pub fn clone(entry: extern fn(usize) -> !, arg: usize, stack_top: usize) -> usize {
+    let context = new_user_thread_context(entry, arg, stack_top);
+    processor().manager().add(context, processor().pid())
+}
+
+/// Cooperatively gives up a timeslice to the OS scheduler.
+pub fn yield_now() {
+    trace!("yield:");
+    processor().yield_now();
+}
+
+/// Blocks unless or until the current thread's token is made available.
+pub fn park() {
+    trace!("park:");
+    processor().manager().sleep(current().id(), 0);
+    processor().yield_now();
+}
+
+/// A handle to a thread.
+pub struct Thread {
+    pid: usize,
+}
+
+impl Thread {
+    /// Atomically makes the handle's token available if it is not already.
+    pub fn unpark(&self) {
+        processor().manager().wakeup(self.pid);
+    }
+    /// Gets the thread's unique identifier.
+    pub fn id(&self) -> usize {
+        self.pid
+    }
+    /// Gets the thread's name, if it was set through a `Builder`.
+    pub fn name(&self) -> Option<String> {
+        THREAD_NAMES.lock().get(&self.pid).cloned()
+    }
+}
+
+/// An owned permission to join on a thread (block on its termination).
+pub struct JoinHandle<T> {
+    thread: Thread,
+    mark: PhantomData<T>,
+}
+
+impl<T> JoinHandle<T> {
+    /// Extracts a handle to the underlying thread.
+    pub fn thread(&self) -> &Thread {
+        &self.thread
+    }
+    /// Waits for the associated thread to finish, returning the value it
+    /// produced or the reason it failed.
+    ///
+    /// Following `std::thread`, a thread that returned normally yields
+    /// `Ok(T)`; one that panicked or was killed yields `Err`, so `join` never
+    /// reconstructs a return value the child never produced.
+    pub fn join(self) -> Result<T, Box<dyn Any + Send>> {
+        loop {
+            match processor().manager().get_status(self.thread.pid) {
+                Some(Status::Exited(exit_code)) => {
+                    processor().manager().remove(self.thread.pid);
+                    return match exit_code {
+                        EXIT_PANIC => Err(Box::new(ThreadError::Panicked)),
+                        EXIT_KILLED => Err(Box::new(ThreadError::Killed)),
+                        // A success marker: the heap pointer to the boxed `T`.
+                        ptr => Ok(unsafe { *Box::from_raw(ptr as *mut T) }),
+                    };
+                }
+                None => return Err(Box::new(ThreadError::NoSuchThread)),
+                _ => (),
+            }
+            processor().manager().wait(current().id(), self.thread.pid);
+            processor().yield_now();
+        }
+    }
+    /// Force construct a JoinHandle struct
+    pub unsafe fn _of(pid: Pid) -> JoinHandle<T> {
+        JoinHandle {
+            thread: Thread { pid },
+            mark: PhantomData,
+        }
+    }
+}
+
+lazy_static! {
+    /// Per-thread local storage, keyed by PID.
+    ///
+    /// Each thread owns a map from a `LocalKey`'s address to its boxed value,
+    /// so different keys of the same thread never collide and distinct threads
+    /// never share a slot.
+    static ref THREAD_LOCALS: Mutex<BTreeMap<usize, BTreeMap<usize, Box<dyn Any + Send>>>>
+        = Mutex::new(BTreeMap::new());
+
+    /// Debug names set through `Builder::name`, keyed by PID.
+    static ref THREAD_NAMES: Mutex<BTreeMap<usize, String>> = Mutex::new(BTreeMap::new());
+}
+
+/// A thread local storage key which owns its contents.
+///
+/// Mirrors `std::thread::LocalKey`: the value is lazily initialized from the
+/// key's initializer on first access within each thread and dropped when the
+/// thread exits.
+pub struct LocalKey<T: 'static> {
+    init: fn() -> T,
+}
+
+impl<T: 'static + Send> LocalKey<T> {
+    /// Construct a key from its initializer. Use the `thread_local!` macro
+    /// instead of calling this directly.
+    pub const fn new(init: fn() -> T) -> Self {
+        LocalKey { init }
+    }
+
+    /// Acquires a reference to the value in this thread's local storage,
+    /// lazily initializing it on first access.
+    pub fn with<F, R>(&'static self, f: F) -> R
+        where F: FnOnce(&T) -> R,
+    {
+        let ptr = self.value_ptr(self as *const Self as usize);
+        // SAFETY: the value lives in a `Box` on the heap for as long as the
+        // current thread does; it is removed only when this thread exits or is
+        // killed (`kill` clears it from another thread, but a killed thread is
+        // never scheduled again, so `f` never resumes on the freed value). The
+        // heap address is stable even if a nested
+        // thread-local access reallocates the table, so the pointer stays valid
+        // for the duration of `f`.
+        f(unsafe { &*ptr })
+    }
+
+    /// Returns a stable pointer to this thread's value for `key`, initializing
+    /// it on first access. The initializer runs with the table lock released,
+    /// so a nested thread-local access inside it neither deadlocks on the
+    /// `spin::Mutex` nor invalidates an outstanding borrow by reallocating the
+    /// map.
+    fn value_ptr(&self, key: usize) -> *const T {
+        let pid = current().id();
+        // Fast path: the value already exists; hand out its (stable) heap address.
+        if let Some(slot) = THREAD_LOCALS.lock().get(&pid).and_then(|m| m.get(&key)) {
+            return slot.downcast_ref::<T>().expect("thread local storage type mismatch");
+        }
+        // Slow path: build the value with the lock released, then publish it,
+        // keeping whichever box wins a concurrent race for the same key.
+        let value: Box<dyn Any + Send> = Box::new((self.init)());
+        let mut table = THREAD_LOCALS.lock();
+        let slot = table.entry(pid).or_insert_with(BTreeMap::new)
+            .entry(key).or_insert(value);
+        slot.downcast_ref::<T>().expect("thread local storage type mismatch")
+    }
+}
+
+/// Releases all thread-local storage owned by the thread `pid`.
+///
+/// Called from every exit path — normal return, panic, and kill — so the
+/// per-PID slot table is dropped in full, leaving neither an empty map nor a
+/// stale `Box<dyn Any>` that a thread later reusing this PID could inherit.
+fn clear_thread_locals(pid: usize) {
+    THREAD_LOCALS.lock().remove(&pid);
+}
+
+/// Declares a new thread local storage key of type `LocalKey`.
+#[macro_export]
+macro_rules! thread_local {
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty = $init:expr; $($rest:tt)*) => {
+        $(#[$attr])* $vis static $name: $crate::thread::LocalKey<$t> =
+            $crate::thread::LocalKey::new(|| $init);
+        $crate::thread_local!($($rest)*);
+    };
+    () => {};
+}
\ No newline at end of file
@@ -0,0 +1,176 @@
+//! Scoped threads, allowing kernel threads to borrow non-`'static` data.
+//!
+//! Mirrors `std::thread::scope`: workers spawned through a [`Scope`] may borrow
+//! from the enclosing stack frame, because [`scope`] does not return until
+//! every child it spawned has exited. The safety of the lifetime extension in
+//! [`Scope::spawn`] rests entirely on that join-before-return guarantee.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use core::mem::{forget, transmute};
+use spin::Mutex as SpinMutex;
+use super::{current, processor, spawn, JoinHandle, Status, ThreadError, EXIT_KILLED, EXIT_PANIC};
+
+/// A scope to spawn scoped threads in.
+///
+/// Created by the [`scope`] function; see its documentation for details.
+pub struct Scope<'env> {
+    /// PIDs of children that the caller did not explicitly join, to be reaped
+    /// before `scope` returns. Draining this to empty is what the join-before-
+    /// return guarantee is driven off.
+    pending: SpinMutex<Vec<usize>>,
+    /// Invariant over the environment lifetime borrowed by the scope body.
+    _env: PhantomData<&'env mut &'env ()>,
+}
+
+/// An owned permission to join on a scoped thread (block on its termination).
+///
+/// The child carries its result back as a raw pointer (a `usize` exit code);
+/// the handle's `T` is recovered in [`ScopedJoinHandle::join`].
+pub struct ScopedJoinHandle<'scope, 'env: 'scope, T> {
+    inner: JoinHandle<usize>,
+    scope: &'scope Scope<'env>,
+    mark: PhantomData<T>,
+}
+
+impl<'env> Scope<'env> {
+    /// Spawns a new thread within the scope, returning a [`ScopedJoinHandle`]
+    /// for it.
+    ///
+    /// Unlike [`super::spawn`], the closure may borrow data that only outlives
+    /// the scope rather than being `'static`.
+    pub fn spawn<'scope, F, T>(&'scope self, f: F) -> ScopedJoinHandle<'scope, 'env, T>
+        where
+            F: FnOnce() -> T + Send + 'env,
+            T: Send + 'env,
+    {
+        // Box the result and carry it back as a raw `usize` so that `T` never
+        // crosses the `super::spawn` boundary — spawn bounds its return type by
+        // `'static`, which a `'env` `T` cannot satisfy, whereas `usize` can.
+        let body: Box<dyn FnOnce() -> usize + Send + 'env> =
+            Box::new(move || Box::into_raw(Box::new(f())) as usize);
+
+        // SAFETY: `scope` does not return until every child spawned here has
+        // reached `Status::Exited`, so the borrowed data outlives the thread,
+        // making the `'env -> 'static` extension of the closure sound.
+        let body: Box<dyn FnOnce() -> usize + Send + 'static> = unsafe { transmute(body) };
+
+        let inner = spawn(move || body());
+        self.pending.lock().push(inner.thread().id());
+        ScopedJoinHandle { inner, scope: self, mark: PhantomData }
+    }
+}
+
+impl<'scope, 'env, T> ScopedJoinHandle<'scope, 'env, T> {
+    /// Waits for the associated scoped thread to finish, returning its result.
+    pub fn join(self) -> Result<T, Box<dyn core::any::Any + Send>> {
+        let pid = self.inner.thread().id();
+        self.scope.forget(pid);
+        // The child hands back the raw pointer to its boxed `T`; reconstruct
+        // and unbox it. A panicked or killed child yields `Err` and never
+        // produced the box, so nothing is reconstructed.
+        self.inner.join().map(|ptr| *unsafe { Box::from_raw(ptr as *mut T) })
+    }
+}
+
+impl<'env> Scope<'env> {
+    /// Drops a PID from the pending set once it has been joined explicitly, so
+    /// the scope cleanup does not reap it a second time.
+    fn forget(&self, pid: usize) {
+        let mut pending = self.pending.lock();
+        if let Some(pos) = pending.iter().position(|&p| p == pid) {
+            pending.swap_remove(pos);
+        }
+    }
+
+    /// Joins every child the caller did not join explicitly, returning the
+    /// first failure reason encountered (if any). Used both on the normal
+    /// return path and, via [`JoinGuard`], while unwinding out of the scope.
+    fn drain(&self) -> Option<ThreadError> {
+        let mut failure = None;
+        loop {
+            let pid = match self.pending.lock().pop() {
+                Some(pid) => pid,
+                None => break,
+            };
+            if let Err(reason) = self.reap(pid) {
+                if failure.is_none() {
+                    failure = Some(reason);
+                }
+            }
+        }
+        failure
+    }
+
+    /// Reaps a child the caller did not join, returning its failure reason if
+    /// any. The boxed return value of a successful child is leaked, since its
+    /// type is not known here; callers that need the value must `join`.
+    fn reap(&self, pid: usize) -> Result<(), ThreadError> {
+        loop {
+            match processor().manager().get_status(pid) {
+                Some(Status::Exited(code)) => {
+                    processor().manager().remove(pid);
+                    return match code {
+                        EXIT_PANIC => Err(ThreadError::Panicked),
+                        EXIT_KILLED => Err(ThreadError::Killed),
+                        _ => Ok(()),
+                    };
+                }
+                None => return Err(ThreadError::NoSuchThread),
+                _ => (),
+            }
+            processor().manager().wait(current().id(), pid);
+            processor().yield_now();
+        }
+    }
+}
+
+/// Drains the scope's outstanding children when dropped.
+///
+/// Holding one of these across the scope body means the join-all runs on a
+/// normal return *and* while unwinding past it, so no child can keep touching
+/// the borrowed frame after [`scope`] has released it — the invariant the
+/// `'env -> 'static` extension in [`Scope::spawn`] relies on.
+struct JoinGuard<'a, 'env: 'a> {
+    scope: &'a Scope<'env>,
+}
+
+impl<'a, 'env> Drop for JoinGuard<'a, 'env> {
+    fn drop(&mut self) {
+        // Failures are dropped here: on the unwinding path we must not panic
+        // again, and on the normal path `scope` drains explicitly and reports.
+        let _ = self.scope.drain();
+    }
+}
+
+/// Creates a scope for spawning scoped threads.
+///
+/// The passed closure is run with a [`Scope`] handle. Every thread spawned on
+/// that handle is guaranteed to have terminated by the time `scope` returns,
+/// so the threads may borrow data from the caller's frame. If any scoped thread
+/// that was not joined explicitly panicked or was killed, the first such
+/// failure is propagated by panicking; the failure of an explicitly joined
+/// thread is reported only through its `join` result.
+pub fn scope<'env, F, T>(f: F) -> T
+    where F: FnOnce(&Scope<'env>) -> T,
+{
+    let scope = Scope {
+        pending: SpinMutex::new(Vec::new()),
+        _env: PhantomData,
+    };
+
+    // Arm the drain-on-drop guard before running the body, so children are
+    // joined even if `f` panics and unwinds straight past the join point.
+    let guard = JoinGuard { scope: &scope };
+    let result = f(&scope);
+
+    // Normal return: defuse the guard and drain explicitly so we can surface
+    // the first child failure (the guard swallows failures to avoid a
+    // double-panic while unwinding).
+    forget(guard);
+    if let Some(reason) = scope.drain() {
+        panic!("scoped thread failed: {:?}", reason);
+    }
+    result
+}
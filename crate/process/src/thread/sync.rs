@@ -0,0 +1,197 @@
+//! Blocking synchronization primitives built on `park`/`unpark`.
+//!
+//! Unlike the `spin` locks used for short critical sections inside the kernel,
+//! these primitives put the calling thread to sleep through the scheduler when
+//! they cannot make progress, so a contended lock does not burn a timeslice.
+//!
+//! Each primitive keeps a spin-protected wait queue of PIDs alongside its
+//! state. The ordering invariant is that a waiter enqueues itself *and* arms
+//! its sleep (`manager().sleep(id, 0)`) while still holding the state lock,
+//! only dropping the lock before it yields the CPU. Because a releaser can pop
+//! a waiter and `unpark` it only after it takes the same state lock — strictly
+//! after the waiter's `sleep` has registered — a wakeup can never precede the
+//! sleep and be lost. Callers additionally re-check the state in a loop after
+//! waking; a spurious wakeup just re-runs the loop, and the re-enqueue skips a
+//! PID already in the queue so a waiter is never listed twice (which would
+//! otherwise let a later release `unpark` a PID that is no longer waiting).
+
+use alloc::collections::VecDeque;
+use core::cell::UnsafeCell;
+use core::ops::{Deref, DerefMut};
+use spin::Mutex as SpinMutex;
+use super::{current, processor, Thread};
+
+/// Internal state of a [`Mutex`]: the locked flag and the queue of waiters.
+struct MutexState {
+    locked: bool,
+    queue: VecDeque<usize>,
+}
+
+/// A mutual exclusion primitive that blocks waiting threads through the
+/// scheduler instead of spinning.
+pub struct Mutex<T: ?Sized> {
+    state: SpinMutex<MutexState>,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for Mutex<T> {}
+unsafe impl<T: ?Sized + Send> Sync for Mutex<T> {}
+
+/// An RAII guard that releases the `Mutex` when dropped.
+pub struct MutexGuard<'a, T: ?Sized + 'a> {
+    mutex: &'a Mutex<T>,
+}
+
+impl<T> Mutex<T> {
+    /// Creates a new mutex in an unlocked state ready for use.
+    pub const fn new(data: T) -> Mutex<T> {
+        Mutex {
+            state: SpinMutex::new(MutexState { locked: false, queue: VecDeque::new() }),
+            data: UnsafeCell::new(data),
+        }
+    }
+}
+
+impl<T: ?Sized> Mutex<T> {
+    /// Acquires the mutex, blocking the current thread until it is available.
+    pub fn lock(&self) -> MutexGuard<T> {
+        loop {
+            let mut state = self.state.lock();
+            if !state.locked {
+                state.locked = true;
+                return MutexGuard { mutex: self };
+            }
+            // Enqueue then park, both ordered under the state lock so an
+            // `unlock` that races us cannot lose our wakeup.
+            let id = current().id();
+            if !state.queue.contains(&id) {
+                state.queue.push_back(id);
+            }
+            // Arm the sleep under the state lock: an `unlock` can only pop us
+            // and call `wakeup` after it takes this lock, i.e. strictly after
+            // our `sleep` registers, so the wakeup cannot be lost.
+            processor().manager().sleep(id, 0);
+            drop(state);
+            processor().yield_now();
+        }
+    }
+}
+
+impl<'a, T: ?Sized> Deref for MutexGuard<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<'a, T: ?Sized> DerefMut for MutexGuard<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
+
+impl<'a, T: ?Sized> Drop for MutexGuard<'a, T> {
+    fn drop(&mut self) {
+        let mut state = self.mutex.state.lock();
+        state.locked = false;
+        if let Some(pid) = state.queue.pop_front() {
+            Thread { pid }.unpark();
+        }
+    }
+}
+
+/// A condition variable, allowing threads to wait for an event while a
+/// companion [`Mutex`] is released.
+pub struct Condvar {
+    queue: SpinMutex<VecDeque<usize>>,
+}
+
+impl Condvar {
+    /// Creates a new condition variable with an empty wait queue.
+    pub const fn new() -> Condvar {
+        Condvar { queue: SpinMutex::new(VecDeque::new()) }
+    }
+
+    /// Atomically releases `guard` and blocks the current thread, re-acquiring
+    /// the mutex before returning.
+    pub fn wait<'a, T: ?Sized>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+        let mutex = guard.mutex;
+        // Enqueue and arm the sleep under the queue lock, then release the
+        // companion mutex. A `notify` can only pop us after it takes the queue
+        // lock, which is strictly after our `sleep` registers, so the wakeup
+        // cannot slip in and be lost.
+        let mut queue = self.queue.lock();
+        queue.push_back(current().id());
+        processor().manager().sleep(current().id(), 0);
+        drop(guard);
+        drop(queue);
+        processor().yield_now();
+        mutex.lock()
+    }
+
+    /// Wakes up one blocked thread waiting on this condition variable.
+    pub fn notify_one(&self) {
+        if let Some(pid) = self.queue.lock().pop_front() {
+            Thread { pid }.unpark();
+        }
+    }
+
+    /// Wakes up all blocked threads waiting on this condition variable.
+    pub fn notify_all(&self) {
+        let mut queue = self.queue.lock();
+        while let Some(pid) = queue.pop_front() {
+            Thread { pid }.unpark();
+        }
+    }
+}
+
+/// Internal state of a [`Semaphore`]: the counter and the queue of waiters.
+struct SemaphoreState {
+    count: isize,
+    queue: VecDeque<usize>,
+}
+
+/// A counting semaphore, parking a thread whenever the counter would become
+/// negative and unparking one waiter on each `release`.
+pub struct Semaphore {
+    state: SpinMutex<SemaphoreState>,
+}
+
+impl Semaphore {
+    /// Creates a new semaphore with the given initial count.
+    pub const fn new(count: isize) -> Semaphore {
+        Semaphore {
+            state: SpinMutex::new(SemaphoreState { count, queue: VecDeque::new() }),
+        }
+    }
+
+    /// Decrements the counter, blocking the current thread while it would go
+    /// negative.
+    pub fn acquire(&self) {
+        loop {
+            let mut state = self.state.lock();
+            if state.count > 0 {
+                state.count -= 1;
+                return;
+            }
+            // Out of permits: enqueue and arm the sleep under the state lock so
+            // a racing `release` cannot lose our wakeup, then re-check on wake.
+            let id = current().id();
+            if !state.queue.contains(&id) {
+                state.queue.push_back(id);
+            }
+            processor().manager().sleep(id, 0);
+            drop(state);
+            processor().yield_now();
+        }
+    }
+
+    /// Increments the counter, waking one waiter if any are blocked.
+    pub fn release(&self) {
+        let mut state = self.state.lock();
+        state.count += 1;
+        if let Some(pid) = state.queue.pop_front() {
+            Thread { pid }.unpark();
+        }
+    }
+}
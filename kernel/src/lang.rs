@@ -0,0 +1,28 @@
+//! Rust language items: the panic handler and the allocation-error hook.
+
+use core::alloc::Layout;
+use core::panic::PanicInfo;
+use process::thread::{self, EXIT_PANIC};
+
+/// Kernel panic handler.
+///
+/// Besides logging the panic, it records a failure exit code for the current
+/// thread so a parent that `join`s it receives an `Err` instead of trying to
+/// reconstruct a return value the thread never produced. The thread is then
+/// parked forever by yielding away; it is never scheduled back.
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    error!("\n\npanic: {}", info);
+    thread::exit_with_failure(EXIT_PANIC);
+    // The thread is now marked `Exited`; yield the CPU so the cooperative
+    // scheduler can run the joiner and other threads instead of spinning here
+    // as the still-"current" thread forever.
+    loop { thread::yield_now(); }
+}
+
+/// Allocation-error hook, invoked when the global allocator cannot satisfy a
+/// request. There is no recovery path in the kernel, so we panic.
+#[alloc_error_handler]
+fn oom(layout: Layout) -> ! {
+    panic!("kernel allocation failed: {:?}", layout);
+}
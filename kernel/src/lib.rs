@@ -10,6 +10,8 @@
 #![feature(panic_info_message)]
 #![feature(global_asm)]
 #![feature(compiler_builtins_lib)]
+#![feature(alloc_error_handler)]
+#![feature(linkage)]
 #![no_std]
 
 extern crate alloc;
@@ -20,6 +22,7 @@ extern crate linked_list_allocator;
 #[macro_use]
 extern crate log;
 extern crate once;
+extern crate process;
 extern crate spin;
 extern crate volatile;
 extern crate xmas_elf;
@@ -29,6 +32,9 @@ use linked_list_allocator::LockedHeap;
 pub mod logging;
 
 mod lang;
+mod memory_syscall;
+mod process_syscall;
+mod syscall;
 
 #[cfg(target_arch = "riscv32")]
 #[path = "arch/riscv32/mod.rs"]
@@ -0,0 +1,164 @@
+//! Kernel side of the `mmap`/`munmap`/`shmem` syscalls.
+//!
+//! These grow or shrink the calling process's [`MemorySet`] on demand:
+//!
+//! - `mmap` reserves an anonymous region in the process page table backed by a
+//!   [`Delay`] handler, so physical frames are allocated lazily the first time
+//!   each page faults rather than up front.
+//! - `munmap` removes the region and lets the `MemorySet` free whatever frames
+//!   were faulted in; for a shared region it instead drops a reference on the
+//!   registry (see below) and only frees the frames once the last mapping goes.
+//! - `shmem` maps a keyed region whose frames are shared: the first caller for
+//!   a key allocates the frames, later callers map the very same physical
+//!   frames into their own page tables, so writes are visible across processes.
+//!
+//! Mappings are placed in the low user region, strictly below the kernel at
+//! [`consts::MEMORY_OFFSET`] (`0x8000_0000` on rv32), so the base address is
+//! always in the non-negative half of an `isize` when handed back to user
+//! space and cannot be mistaken for an error code.
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use spin::Mutex;
+
+use crate::consts;
+use crate::memory::{Frame, GlobalFrameAlloc, MemoryAttr};
+use crate::memory::handler::{ByFrame, Delay};
+use crate::process::{current_pid, current_thread};
+
+/// `prot` bit requesting write access (mirrors the user-side `PROT_WRITE`).
+const PROT_WRITE: usize = 0x2;
+
+/// Syscall ids for the memory calls, matching the user-side `SyscallId`.
+const SYS_MMAP: usize = 20;
+const SYS_MUNMAP: usize = 21;
+const SYS_SHMEM: usize = 22;
+
+/// Routes the three memory-management syscalls to their handlers, yielding
+/// `None` for any other id so the caller keeps looking.
+pub fn dispatch(id: usize, args: [usize; 6]) -> Option<isize> {
+    match id {
+        SYS_MMAP => Some(sys_mmap(args[0], args[1], args[2], args[3])),
+        SYS_MUNMAP => Some(sys_munmap(args[0], args[1])),
+        SYS_SHMEM => Some(sys_shmem(args[0], args[1], args[2])),
+        _ => None,
+    }
+}
+
+/// Rounds `addr` down / `addr + len` up to page boundaries.
+fn page_range(addr: usize, len: usize) -> (usize, usize) {
+    let start = addr & !(consts::PAGE_SIZE - 1);
+    let end = (addr + len + consts::PAGE_SIZE - 1) & !(consts::PAGE_SIZE - 1);
+    (start, end)
+}
+
+/// Returns `true` if `[addr, addr + len)` lies wholly in the usable user range
+/// (above the null page and below the kernel).
+fn in_user_range(addr: usize, len: usize) -> bool {
+    addr >= consts::PAGE_SIZE
+        && len != 0
+        && addr.checked_add(len).map_or(false, |end| end <= consts::MEMORY_OFFSET)
+}
+
+/// Translates a user `prot` bitmask into a [`MemoryAttr`] for a user region.
+fn attr_of(prot: usize) -> MemoryAttr {
+    let attr = MemoryAttr::default().user();
+    if prot & PROT_WRITE != 0 { attr.writable() } else { attr.readonly() }
+}
+
+/// Reserves an anonymous, demand-paged region of `len` bytes and returns its
+/// base address, or a negative error code.
+pub fn sys_mmap(addr: usize, len: usize, prot: usize, _flags: usize) -> isize {
+    if len == 0 {
+        return -(consts::EINVAL as isize);
+    }
+    let thread = current_thread();
+    let mut vm = thread.vm.lock();
+    // A hint of 0 lets the kernel choose; a fixed address must be a valid,
+    // kernel-free user address.
+    let base = if addr == 0 { vm.find_free_area(len) } else { addr };
+    if !in_user_range(base, len) {
+        return -(consts::EINVAL as isize);
+    }
+    let (start, end) = page_range(base, len);
+    // Lazily backed: `Delay` allocates a frame on the first fault of each page.
+    vm.push(start, end, attr_of(prot), Delay::new(GlobalFrameAlloc), "mmap");
+    base as isize
+}
+
+/// Tears down the region of `len` bytes at `addr`, freeing any faulted-in
+/// frames. A shared region is unmapped from this process and its registry
+/// reference dropped instead, so its frames survive until the last mapping is
+/// gone. Returns `0` on success.
+pub fn sys_munmap(addr: usize, len: usize) -> isize {
+    let (start, end) = page_range(addr, len);
+    let thread = current_thread();
+    thread.vm.lock().pop(start, end);
+    // If this was a shared mapping, drop our reference; the frames themselves
+    // stay alive in the registry until no process maps them any more.
+    if let Some(key) = SHMEM_MAPPINGS.lock().remove(&(current_pid(), start)) {
+        let mut shmem = SHMEM.lock();
+        if let Some(region) = shmem.get_mut(&key) {
+            region.refcount -= 1;
+            if region.refcount == 0 {
+                shmem.remove(&key);
+            }
+        }
+    }
+    0
+}
+
+/// A shared region: the frames are held alive by the registry (so they outlive
+/// any single mapping) until `refcount` — the number of live mappings of the
+/// region, one per `shmem` call not yet unmapped — drops to zero.
+struct SharedRegion {
+    frames: Vec<Arc<Frame>>,
+    refcount: usize,
+}
+
+lazy_static::lazy_static! {
+    /// Keyed shared regions, indexed by the user-supplied `key`.
+    static ref SHMEM: Mutex<BTreeMap<usize, SharedRegion>> = Mutex::new(BTreeMap::new());
+    /// Per-mapping back-reference from `(pid, base)` to its `key`, so `munmap`
+    /// can recognise a shared region and decrement the right registry entry.
+    static ref SHMEM_MAPPINGS: Mutex<BTreeMap<(usize, usize), usize>> = Mutex::new(BTreeMap::new());
+}
+
+/// Maps the shared region named by `key` into the caller's address space,
+/// allocating the frames on first use and reusing them afterwards. Returns the
+/// base address, or a negative error code.
+pub fn sys_shmem(key: usize, len: usize, prot: usize) -> isize {
+    if len == 0 {
+        return -(consts::EINVAL as isize);
+    }
+    let pages = (len + consts::PAGE_SIZE - 1) / consts::PAGE_SIZE;
+
+    // Take (or create) the region and bump its reference count while holding
+    // the registry lock, cloning the frame handles out for the actual mapping.
+    let frames: Vec<Arc<Frame>> = {
+        let mut shmem = SHMEM.lock();
+        let region = shmem.entry(key).or_insert_with(|| SharedRegion {
+            frames: (0..pages)
+                .map(|_| Arc::new(GlobalFrameAlloc.alloc().expect("shmem: out of frames")))
+                .collect(),
+            refcount: 0,
+        });
+        region.refcount += 1;
+        region.frames.clone()
+    };
+
+    let thread = current_thread();
+    let mut vm = thread.vm.lock();
+    let base = vm.find_free_area(len);
+    let (start, _end) = page_range(base, len);
+    // Map each shared frame explicitly so all processes point at the same
+    // physical memory; no demand paging here since the frames already exist.
+    for (i, frame) in frames.iter().enumerate() {
+        let va = start + i * consts::PAGE_SIZE;
+        vm.push(va, va + consts::PAGE_SIZE, attr_of(prot),
+                ByFrame::new_shared(frame.clone()), "shmem");
+    }
+    SHMEM_MAPPINGS.lock().insert((current_pid(), start), key);
+    base as isize
+}
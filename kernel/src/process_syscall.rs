@@ -0,0 +1,42 @@
+//! Kernel side of the process/thread creation syscalls.
+//!
+//! `clone` creates a new schedulable task that shares the caller's address
+//! space — unlike `fork`, which copies it — so the two threads see the same
+//! global data. The user passes an entry point, an argument, and the top of a
+//! freshly allocated user stack; the new task starts at `entry(arg)` on that
+//! stack and is registered under the same address space as the parent.
+
+use process::thread;
+
+/// Syscall id for `clone`, matching the user-side `SyscallId`.
+const SYS_CLONE: usize = 5;
+/// Syscall id for `kill`, matching the user-side `SyscallId`.
+const SYS_KILL: usize = 12;
+
+/// Routes the process/thread syscalls (`clone`, `kill`) to their handlers,
+/// yielding `None` for any other id so the caller keeps looking.
+pub fn dispatch(id: usize, args: [usize; 6]) -> Option<isize> {
+    match id {
+        SYS_CLONE => Some(sys_clone(args[0], args[1], args[2])),
+        SYS_KILL => Some(sys_kill(args[0])),
+        _ => None,
+    }
+}
+
+/// Kills the thread `pid`, recording a killed-exit marker so a joiner sees a
+/// failure rather than a bogus return value. Returns `0`.
+fn sys_kill(pid: usize) -> isize {
+    thread::kill(pid);
+    0
+}
+
+/// Creates a new user thread sharing the caller's address space and returns
+/// its TID. `entry` is reinterpreted as the user entry point, `arg` is placed
+/// in the argument register, and `stack_top` becomes the new stack pointer.
+fn sys_clone(entry: usize, arg: usize, stack_top: usize) -> isize {
+    // SAFETY: `entry` is a user-space code address supplied by the caller; the
+    // new thread runs it in user mode, so a bogus value faults that thread
+    // rather than the kernel.
+    let entry: extern fn(usize) -> ! = unsafe { core::mem::transmute(entry) };
+    thread::clone(entry, arg, stack_top) as isize
+}
@@ -0,0 +1,31 @@
+//! Top-level system-call dispatch.
+//!
+//! The trap handler decodes the syscall id and its (up to six) register
+//! arguments and calls [`syscall`], which offers the call to each subsystem
+//! module in turn before falling through to the legacy per-id table.
+
+use crate::consts;
+use crate::{memory_syscall, process_syscall};
+
+/// Handles a system call and returns the value to place in the caller's result
+/// register. The call is offered to the memory (`mmap`/`munmap`/`shmem`) and
+/// process (`clone`/`kill`) modules in turn; anything unclaimed falls through
+/// to [`legacy_syscall`].
+#[no_mangle]
+pub extern "C" fn syscall(id: usize, args: [usize; 6]) -> isize {
+    if let Some(ret) = memory_syscall::dispatch(id, args) {
+        return ret;
+    }
+    if let Some(ret) = process_syscall::dispatch(id, args) {
+        return ret;
+    }
+    legacy_syscall(id, args)
+}
+
+#[linkage = "weak"]
+#[no_mangle]
+/// The pre-existing syscall table, handling every id the subsystem modules do
+/// not claim. Provided by the full kernel; the weak default rejects the call.
+fn legacy_syscall(_id: usize, _args: [usize; 6]) -> isize {
+    -(consts::ENOSYS as isize)
+}
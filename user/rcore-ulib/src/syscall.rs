@@ -54,6 +54,15 @@ pub fn sys_fork() -> i32 {
     sys_call(SyscallId::Fork, 0, 0, 0, 0, 0, 0)
 }
 
+/// Create a new thread sharing the caller's address space.
+///
+/// The new thread starts at `entry` with `arg` in its argument register and
+/// `stack_top` as its stack pointer, but shares the caller's page table so all
+/// global data is shared. Return the new thread's TID.
+pub fn sys_clone(entry: extern fn(usize) -> !, arg: usize, stack_top: usize) -> i32 {
+    sys_call(SyscallId::Clone, entry as usize, arg, stack_top, 0, 0, 0)
+}
+
 /// Wait the process exit.
 /// Return the PID. Store exit code to `code` if it's not null.
 pub fn sys_wait(pid: usize, code: *mut i32) -> i32 {
@@ -90,6 +99,32 @@ pub fn sys_putc(c: u8) -> i32 {
     sys_call(SyscallId::Putc, c as usize, 0, 0, 0, 0, 0)
 }
 
+/// Map a region of `len` bytes into the process's address space.
+/// The region is demand-paged: frames are allocated on first access.
+///
+/// Returns the base address of the mapping, or a negative error code. The
+/// mapping lands in the low user region (below `0x8000_0000`), so the address
+/// always fits in the non-negative half of an `isize` and never aliases an
+/// error code.
+pub fn sys_mmap(addr: usize, len: usize, prot: usize, flags: usize) -> isize {
+    sys_call(SyscallId::Mmap, addr, len, prot, flags, 0, 0) as isize
+}
+
+/// Unmap the region of `len` bytes at `addr`, freeing its frames.
+pub fn sys_munmap(addr: usize, len: usize) -> i32 {
+    sys_call(SyscallId::Munmap, addr, len, 0, 0, 0, 0)
+}
+
+/// Map the shared region named by `key` into the process's address space.
+/// Processes passing the same `key` share the underlying physical frames.
+///
+/// Returns the base address of the mapping, or a negative error code; like
+/// [`sys_mmap`] the address lives in the low user region so it stays
+/// non-negative.
+pub fn sys_shmem(key: usize, len: usize, prot: usize) -> isize {
+    sys_call(SyscallId::Shmem, key, len, prot, 0, 0, 0) as isize
+}
+
 #[allow(dead_code)]
 enum SyscallId{
     Exit = 1,